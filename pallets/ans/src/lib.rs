@@ -2,6 +2,12 @@
 
 pub use pallet::*;
 
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
 use frame_support::{pallet_prelude::OptionQuery, traits::{Currency, ReservableCurrency}};
 
 type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
@@ -11,6 +17,9 @@ type BalanceOf<T> = <<T as Config>::Currency as Currency<AccountIdOf<T>>>::Balan
 pub mod pallet {
 	use super::*;
 	use frame_support::sp_runtime;
+use frame_support::sp_runtime::traits::Saturating;
+use frame_support::sp_runtime::traits::Verify;
+use frame_support::traits::Contains;
 use frame_support::traits::WithdrawReasons;
 use frame_support::{pallet_prelude::*, storage::child::exists};
 	use frame_system::pallet_prelude::*;
@@ -31,6 +40,20 @@ use frame_support::{pallet_prelude::*, storage::child::exists};
 
 		/// The currency trait.
 		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// How many blocks a reservation lasts before it must be renewed.
+		#[pallet::constant]
+		type LeasePeriod: Get<BlockNumberFor<Self>>;
+
+		/// The maximum number of names a single account may own at once.
+		#[pallet::constant]
+		type MaxNamesPerAccount: Get<u32>;
+
+		/// The off-chain signature scheme used to verify pre-signed name grants.
+		type Signature: Verify<Signer = Self::AccountId> + Parameter;
+
+		/// The set of accounts allowed to reserve names when membership is required.
+		type Registrars: Contains<Self::AccountId>;
 	}
 
 	#[pallet::event]
@@ -51,6 +74,78 @@ use frame_support::{pallet_prelude::*, storage::child::exists};
 			// name being reserved
 			name: Vec<u8>
 		},
+		/// A name was released and its deposit returned to the owner.
+		Released {
+			/// The account that released the name.
+			who: T::AccountId,
+			// name being released
+			name: Vec<u8>
+		},
+		/// A name's lease was extended by another lease period.
+		Renewed {
+			/// The owner who renewed the name.
+			who: T::AccountId,
+			// name being renewed
+			name: Vec<u8>,
+			/// The block at which the new lease expires.
+			expires_at: BlockNumberFor<T>,
+		},
+		/// An expired name was reclaimed by a new owner.
+		Expired {
+			/// The account that previously owned the name.
+			who: T::AccountId,
+			// name that expired
+			name: Vec<u8>
+		},
+		/// A pre-signed grant was redeemed for a name.
+		Claimed {
+			/// The account that received the name.
+			who: T::AccountId,
+			// name being claimed
+			name: Vec<u8>
+		},
+		/// A name was listed for sale.
+		Listed {
+			/// The account selling the name.
+			who: T::AccountId,
+			// name being listed
+			name: Vec<u8>,
+			/// The asking price.
+			price: BalanceOf<T>,
+		},
+		/// A name was taken off the market.
+		Unlisted {
+			/// The account that unlisted the name.
+			who: T::AccountId,
+			// name being unlisted
+			name: Vec<u8>
+		},
+		/// A listed name was sold.
+		Sold {
+			// name that was sold
+			name: Vec<u8>,
+			/// The previous owner.
+			from: T::AccountId,
+			/// The new owner.
+			to: T::AccountId,
+			/// The price paid.
+			price: BalanceOf<T>,
+		},
+		/// An account was authorized to sign pre-signed name grants.
+		GrantSignerAdded {
+			/// The account added as a grant signer.
+			who: T::AccountId,
+		},
+		/// An account's grant-signing authorization was revoked.
+		GrantSignerRemoved {
+			/// The account removed as a grant signer.
+			who: T::AccountId,
+		},
+		/// Membership gating for `reserve` and `claim_presigned` was toggled.
+		MembershipRequiredSet {
+			/// Whether membership is now required.
+			required: bool,
+		},
 	}
 
 	#[pallet::error]
@@ -65,15 +160,31 @@ use frame_support::{pallet_prelude::*, storage::child::exists};
 		NotFound,
 		/// Not the owner of this reservation.
 		NotOwner,
-		/// the reservation account is not configured
-		ReserveAccountNotConfigured,
+		/// Cannot transfer a name to its current owner.
+		CannotTransferToSelf,
+		/// This account already owns the maximum number of names allowed.
+		TooManyNames,
+		/// The pre-signed grant's deadline has already passed.
+		GrantExpired,
+		/// The grant's signature does not match the claimed signer.
+		InvalidSignature,
+		/// The signer is not an authorized grant signer.
+		UnauthorizedSigner,
+		/// This grant has already been redeemed.
+		GrantAlreadyClaimed,
+		/// This name is not listed for sale.
+		NotListed,
+		/// An owner cannot buy their own name.
+		CannotBuyOwnName,
+		/// This account is not an approved registrar and membership is required.
+		NotAllowed,
 	}
 
 	#[pallet::genesis_config]
 	#[derive(frame_support::DefaultNoBound)]
 	pub struct GenesisConfig<T: Config> {
 		pub reservation_fee: BalanceOf<T>,
-		pub reservation_account: Option<T::AccountId>
+		pub authorized_signers: Vec<T::AccountId>,
 	}
 
 	#[pallet::genesis_build]
@@ -82,28 +193,81 @@ use frame_support::{pallet_prelude::*, storage::child::exists};
             // use &self to access fields.
 			ReservationFee::<T>::put(self.reservation_fee);
 
-			match &self.reservation_account {
-				Some(account) => {
-					ReservationAccount::<T>::put(account);
-				},
-				None => {}
+			for signer in &self.authorized_signers {
+				AuthorizedSigner::<T>::insert(signer, ());
 			}
         }
     }
 
-	/// This maps names to accounts.
+	/// A name grant signed off-chain by an authorized signer. Any account may submit it; the
+	/// name goes to its recipient.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+	pub struct PreSignedGrant<AccountId, BlockNumber> {
+		/// The name being granted.
+		pub name: Vec<u8>,
+		/// The account that becomes the name's owner when this grant is redeemed.
+		pub recipient: AccountId,
+		/// The last block at which this grant may be redeemed.
+		pub deadline: BlockNumber,
+	}
+
+	/// The owner of a name together with the balance reserved to hold it.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct AnsEntry<AccountId, Balance, BlockNumber> {
+		/// The account that owns this name.
+		pub owner: AccountId,
+		/// The balance reserved against the owner to hold this name.
+		pub deposit: Balance,
+		/// The block at which this name's lease expires.
+		pub expires_at: BlockNumber,
+	}
+
+	/// This maps names to their owner, reserved deposit and lease expiry.
 	#[pallet::storage]
-	#[pallet::getter(fn get_entry)]
-	pub(super) type AnsOf<T: Config> =
-		StorageMap<_, Twox64Concat, BoundedVec<u8, T::MaxLength>, T::AccountId>;
+	pub(super) type AnsOf<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BoundedVec<u8, T::MaxLength>,
+		AnsEntry<T::AccountId, BalanceOf<T>, BlockNumberFor<T>>,
+	>;
+
+	/// This maps an account to the names it currently owns, for reverse resolution.
+	#[pallet::storage]
+	#[pallet::getter(fn get_names_of)]
+	pub(super) type NamesOf<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		BoundedVec<BoundedVec<u8, T::MaxLength>, T::MaxNamesPerAccount>,
+		ValueQuery,
+	>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn get_reservation_fee)]
 	pub type ReservationFee<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 
+	/// The set of accounts whose signature is accepted on a pre-signed name grant.
+	#[pallet::storage]
+	pub(super) type AuthorizedSigner<T: Config> =
+		StorageMap<_, Twox64Concat, T::AccountId, (), OptionQuery>;
+
+	/// Names that have already had a pre-signed grant redeemed, to prevent replay. Cleared
+	/// whenever the name's ownership changes, so replay protection doesn't outlive the grant.
+	#[pallet::storage]
+	#[pallet::getter(fn is_grant_used)]
+	pub(super) type UsedGrants<T: Config> =
+		StorageMap<_, Twox64Concat, BoundedVec<u8, T::MaxLength>, (), OptionQuery>;
+
+	/// Names listed for sale, mapped to their asking price.
+	#[pallet::storage]
+	#[pallet::getter(fn get_listing)]
+	pub(super) type Listings<T: Config> =
+		StorageMap<_, Twox64Concat, BoundedVec<u8, T::MaxLength>, BalanceOf<T>, OptionQuery>;
+
+	/// Whether only approved `Registrars` may reserve or claim names.
 	#[pallet::storage]
-	#[pallet::getter(fn get_reservation_account)]
-	pub type ReservationAccount<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+	#[pallet::getter(fn is_membership_required)]
+	pub type RequireMembership<T> = StorageValue<_, bool, ValueQuery>;
 
 	#[pallet::pallet]
 	pub struct Pallet<T>(_);
@@ -115,42 +279,57 @@ use frame_support::{pallet_prelude::*, storage::child::exists};
 		#[pallet::weight({50_000_000})]
 		pub fn reserve(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			if RequireMembership::<T>::get() {
+				ensure!(T::Registrars::contains(&sender), Error::<T>::NotAllowed);
+			}
 			let bounded_name: BoundedVec<_, _> =
 				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
 			ensure!(bounded_name.len() >= T::MinLength::get() as usize, Error::<T>::TooShort);
-			ensure!(!<AnsOf<T>>::contains_key(bounded_name.clone()), Error::<T>::AlreadyReserved);
 
-			let reserve_account_opt = ReservationAccount::<T>::get();
-			match reserve_account_opt {
-				None => {
-					return frame_support::fail!(Error::<T>::ReserveAccountNotConfigured);
-				},
-				Some(reservation_account) => {
-					let fee = ReservationFee::<T>::get();
-					T::Currency::transfer(&sender, &reservation_account, fee, frame_support::traits::ExistenceRequirement::AllowDeath)?;
-					<AnsOf<T>>::insert(&bounded_name, sender.clone() );
-					Self::deposit_event(Event::<T>::Reserved { who: sender, name: name });
-					Ok(())
-				}
+			if let Some(existing) = <AnsOf<T>>::get(bounded_name.clone()) {
+				let now = frame_system::Pallet::<T>::block_number();
+				ensure!(existing.expires_at < now, Error::<T>::AlreadyReserved);
+				T::Currency::unreserve(&existing.owner, existing.deposit);
+				Self::remove_name_from_owner(&existing.owner, &bounded_name);
+				Listings::<T>::remove(&bounded_name);
+				UsedGrants::<T>::remove(&bounded_name);
+				Self::deposit_event(Event::<T>::Expired { who: existing.owner, name: name.clone() });
 			}
+
+			let fee = ReservationFee::<T>::get();
+			Self::add_name_to_owner(&sender, &bounded_name)?;
+			T::Currency::reserve(&sender, fee)?;
+			let expires_at =
+				frame_system::Pallet::<T>::block_number().saturating_add(T::LeasePeriod::get());
+			<AnsOf<T>>::insert(&bounded_name, AnsEntry { owner: sender.clone(), deposit: fee, expires_at });
+			Self::deposit_event(Event::<T>::Reserved { who: sender, name: name });
+			Ok(())
 		}
 
 		#[pallet::call_index(1)]
 		#[pallet::weight({50_000_000})]
 		pub fn transfer_to(origin: OriginFor<T>, name: Vec<u8>, to: T::AccountId) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			
+
 			let bounded_name: BoundedVec<_, _> =
 				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
 
 			// make sure that the current owner is sender.
-			let existing = <AnsOf<T>>::get(bounded_name.clone());
+			let existing = Self::get_entry(bounded_name.clone());
 			match existing {
-				Some(current_owner) => {
-					
-					ensure!(sender == current_owner, Error::<T>::NotOwner);
-					<AnsOf<T>>::insert(&bounded_name, to.clone());
-					Self::deposit_event(Event::<T>::Transferred { from: sender, to: to.clone(), name: name });				
+				Some(mut entry) => {
+
+					ensure!(sender == entry.owner, Error::<T>::NotOwner);
+					ensure!(to != sender, Error::<T>::CannotTransferToSelf);
+					T::Currency::reserve(&to, entry.deposit)?;
+					T::Currency::unreserve(&entry.owner, entry.deposit);
+					Self::remove_name_from_owner(&entry.owner, &bounded_name);
+					Self::add_name_to_owner(&to, &bounded_name)?;
+					entry.owner = to.clone();
+					<AnsOf<T>>::insert(&bounded_name, entry);
+					Listings::<T>::remove(&bounded_name);
+					UsedGrants::<T>::remove(&bounded_name);
+					Self::deposit_event(Event::<T>::Transferred { from: sender, to: to.clone(), name: name });
 
 				},
 				None => {
@@ -160,5 +339,237 @@ use frame_support::{pallet_prelude::*, storage::child::exists};
 
 			Ok(())
 		}
+
+		/// Give up a previously reserved name, returning its deposit to the owner.
+		#[pallet::call_index(2)]
+		#[pallet::weight({50_000_000})]
+		pub fn release(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let bounded_name: BoundedVec<_, _> =
+				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			let entry = Self::get_entry(bounded_name.clone()).ok_or(Error::<T>::NotFound)?;
+			ensure!(sender == entry.owner, Error::<T>::NotOwner);
+
+			<AnsOf<T>>::remove(&bounded_name);
+			Self::remove_name_from_owner(&entry.owner, &bounded_name);
+			Listings::<T>::remove(&bounded_name);
+			UsedGrants::<T>::remove(&bounded_name);
+			T::Currency::unreserve(&entry.owner, entry.deposit);
+			Self::deposit_event(Event::<T>::Released { who: sender, name: name });
+			Ok(())
+		}
+
+		/// Extend the lease on an owned name by another lease period, charging the reservation
+		/// fee again.
+		#[pallet::call_index(3)]
+		#[pallet::weight({50_000_000})]
+		pub fn renew(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let bounded_name: BoundedVec<_, _> =
+				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			let mut entry = Self::get_entry(bounded_name.clone()).ok_or(Error::<T>::NotFound)?;
+			ensure!(sender == entry.owner, Error::<T>::NotOwner);
+
+			let fee = ReservationFee::<T>::get();
+			T::Currency::reserve(&sender, fee)?;
+			entry.deposit = entry.deposit.saturating_add(fee);
+			entry.expires_at = entry.expires_at.saturating_add(T::LeasePeriod::get());
+			<AnsOf<T>>::insert(&bounded_name, entry.clone());
+			Self::deposit_event(Event::<T>::Renewed { who: sender, name: name, expires_at: entry.expires_at });
+			Ok(())
+		}
+
+		/// Redeem a name grant signed off-chain by an authorized signer.
+		#[pallet::call_index(4)]
+		#[pallet::weight({50_000_000})]
+		pub fn claim_presigned(
+			origin: OriginFor<T>,
+			data: PreSignedGrant<T::AccountId, BlockNumberFor<T>>,
+			signature: T::Signature,
+			signer: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			if RequireMembership::<T>::get() {
+				ensure!(T::Registrars::contains(&caller), Error::<T>::NotAllowed);
+			}
+
+			ensure!(AuthorizedSigner::<T>::contains_key(&signer), Error::<T>::UnauthorizedSigner);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= data.deadline,
+				Error::<T>::GrantExpired
+			);
+			ensure!(signature.verify(&data.encode()[..], &signer), Error::<T>::InvalidSignature);
+
+			let bounded_name: BoundedVec<_, _> =
+				data.name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+			ensure!(bounded_name.len() >= T::MinLength::get() as usize, Error::<T>::TooShort);
+			ensure!(!UsedGrants::<T>::contains_key(&bounded_name), Error::<T>::GrantAlreadyClaimed);
+			ensure!(Self::resolve(&bounded_name).is_none(), Error::<T>::AlreadyReserved);
+
+			if let Some(stale) = <AnsOf<T>>::get(bounded_name.clone()) {
+				T::Currency::unreserve(&stale.owner, stale.deposit);
+				Self::remove_name_from_owner(&stale.owner, &bounded_name);
+				Listings::<T>::remove(&bounded_name);
+				UsedGrants::<T>::remove(&bounded_name);
+				Self::deposit_event(Event::<T>::Expired { who: stale.owner, name: data.name.clone() });
+			}
+
+			UsedGrants::<T>::insert(&bounded_name, ());
+			Self::add_name_to_owner(&data.recipient, &bounded_name)?;
+			let expires_at =
+				frame_system::Pallet::<T>::block_number().saturating_add(T::LeasePeriod::get());
+			<AnsOf<T>>::insert(
+				&bounded_name,
+				AnsEntry { owner: data.recipient.clone(), deposit: BalanceOf::<T>::default(), expires_at },
+			);
+			Self::deposit_event(Event::<T>::Claimed { who: data.recipient, name: data.name });
+			Ok(())
+		}
+
+		/// List an owned name for sale at the given price.
+		#[pallet::call_index(5)]
+		#[pallet::weight({50_000_000})]
+		pub fn list_for_sale(origin: OriginFor<T>, name: Vec<u8>, price: BalanceOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let bounded_name: BoundedVec<_, _> =
+				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			let entry = Self::get_entry(bounded_name.clone()).ok_or(Error::<T>::NotFound)?;
+			ensure!(sender == entry.owner, Error::<T>::NotOwner);
+
+			Listings::<T>::insert(&bounded_name, price);
+			Self::deposit_event(Event::<T>::Listed { who: sender, name: name, price });
+			Ok(())
+		}
+
+		/// Take a name off the market.
+		#[pallet::call_index(6)]
+		#[pallet::weight({50_000_000})]
+		pub fn unlist(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let bounded_name: BoundedVec<_, _> =
+				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			let entry = Self::get_entry(bounded_name.clone()).ok_or(Error::<T>::NotFound)?;
+			ensure!(sender == entry.owner, Error::<T>::NotOwner);
+			ensure!(Listings::<T>::contains_key(&bounded_name), Error::<T>::NotListed);
+
+			Listings::<T>::remove(&bounded_name);
+			Self::deposit_event(Event::<T>::Unlisted { who: sender, name: name });
+			Ok(())
+		}
+
+		/// Buy a name that is currently listed for sale.
+		#[pallet::call_index(7)]
+		#[pallet::weight({50_000_000})]
+		pub fn buy(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+
+			let bounded_name: BoundedVec<_, _> =
+				name.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			let mut entry = Self::get_entry(bounded_name.clone()).ok_or(Error::<T>::NotFound)?;
+			let price = Listings::<T>::get(&bounded_name).ok_or(Error::<T>::NotListed)?;
+			ensure!(buyer != entry.owner, Error::<T>::CannotBuyOwnName);
+
+			T::Currency::transfer(
+				&buyer,
+				&entry.owner,
+				price,
+				frame_support::traits::ExistenceRequirement::KeepAlive,
+			)?;
+			T::Currency::reserve(&buyer, entry.deposit)?;
+			T::Currency::unreserve(&entry.owner, entry.deposit);
+
+			Self::add_name_to_owner(&buyer, &bounded_name)?;
+			Self::remove_name_from_owner(&entry.owner, &bounded_name);
+			let seller = entry.owner;
+			entry.owner = buyer.clone();
+			<AnsOf<T>>::insert(&bounded_name, entry);
+			Listings::<T>::remove(&bounded_name);
+			UsedGrants::<T>::remove(&bounded_name);
+
+			Self::deposit_event(Event::<T>::Sold { name: name, from: seller, to: buyer, price });
+			Ok(())
+		}
+
+		/// Turn membership gating on or off for `reserve` and `claim_presigned`.
+		#[pallet::call_index(8)]
+		#[pallet::weight({10_000_000})]
+		pub fn set_membership_required(origin: OriginFor<T>, required: bool) -> DispatchResult {
+			ensure_root(origin)?;
+			RequireMembership::<T>::put(required);
+			Self::deposit_event(Event::<T>::MembershipRequiredSet { required });
+			Ok(())
+		}
+
+		/// Authorize an account's signature on pre-signed name grants.
+		#[pallet::call_index(9)]
+		#[pallet::weight({10_000_000})]
+		pub fn add_grant_signer(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			AuthorizedSigner::<T>::insert(&who, ());
+			Self::deposit_event(Event::<T>::GrantSignerAdded { who });
+			Ok(())
+		}
+
+		/// Revoke an account's authorization to sign pre-signed name grants.
+		#[pallet::call_index(10)]
+		#[pallet::weight({10_000_000})]
+		pub fn remove_grant_signer(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			AuthorizedSigner::<T>::remove(&who);
+			Self::deposit_event(Event::<T>::GrantSignerRemoved { who });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Look up a name's entry, treating an expired lease as absent.
+		pub fn get_entry(
+			name: BoundedVec<u8, T::MaxLength>,
+		) -> Option<AnsEntry<T::AccountId, BalanceOf<T>, BlockNumberFor<T>>> {
+			let entry = <AnsOf<T>>::get(name)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			if entry.expires_at < now {
+				None
+			} else {
+				Some(entry)
+			}
+		}
+
+		/// Resolve a name to its current owner, treating an expired lease as absent.
+		pub fn resolve(name: &BoundedVec<u8, T::MaxLength>) -> Option<T::AccountId> {
+			Self::get_entry(name.clone()).map(|entry| entry.owner)
+		}
+
+		/// Whether `who` is an authorized signer of pre-signed name grants.
+		pub fn is_authorized_signer(who: T::AccountId) -> bool {
+			AuthorizedSigner::<T>::contains_key(who)
+		}
+
+		/// Record in the reverse index that `owner` now owns `name`.
+		fn add_name_to_owner(
+			owner: &T::AccountId,
+			name: &BoundedVec<u8, T::MaxLength>,
+		) -> DispatchResult {
+			NamesOf::<T>::try_mutate(owner, |names| {
+				names.try_push(name.clone()).map_err(|_| Error::<T>::TooManyNames)
+			})?;
+			Ok(())
+		}
+
+		/// Remove `name` from `owner`'s entry in the reverse index.
+		fn remove_name_from_owner(owner: &T::AccountId, name: &BoundedVec<u8, T::MaxLength>) {
+			NamesOf::<T>::mutate(owner, |names| {
+				names.retain(|existing| existing != name);
+			});
+		}
 	}
 }
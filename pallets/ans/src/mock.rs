@@ -0,0 +1,111 @@
+use crate as pallet_ans;
+use frame_support::{
+	derive_impl,
+	traits::{ConstU32, ConstU64, Contains},
+};
+use sp_core::sr25519;
+use sp_runtime::{
+	traits::{IdentifyAccount, IdentityLookup, Verify},
+	BuildStorage, MultiSignature,
+};
+use sp_std::cell::RefCell;
+use sp_std::vec::Vec;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type Signature = MultiSignature;
+pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub type Balance = u64;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		Ans: pallet_ans,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+impl pallet_balances::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type Balance = Balance;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type MaxLocks = ConstU32<50>;
+	type MaxReserves = ConstU32<50>;
+	type ReserveIdentifier = [u8; 8];
+	type FreezeIdentifier = ();
+	type MaxFreezes = ();
+	type RuntimeHoldReason = ();
+	type RuntimeFreezeReason = ();
+}
+
+thread_local! {
+	/// The set of accounts `RegistrarSet::contains` treats as authorized registrars.
+	static REGISTRARS: RefCell<Vec<AccountId>> = RefCell::new(Vec::new());
+}
+
+/// A `Contains` impl backed by a thread-local set, so tests can control membership gating
+/// without needing a whole separate pallet.
+pub struct RegistrarSet;
+impl Contains<AccountId> for RegistrarSet {
+	fn contains(who: &AccountId) -> bool {
+		REGISTRARS.with(|r| r.borrow().contains(who))
+	}
+}
+
+pub fn set_registrars(registrars: Vec<AccountId>) {
+	REGISTRARS.with(|r| *r.borrow_mut() = registrars);
+}
+
+impl pallet_ans::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type MinLength = ConstU32<3>;
+	type MaxLength = ConstU32<32>;
+	type Currency = Balances;
+	type LeasePeriod = ConstU64<10>;
+	type MaxNamesPerAccount = ConstU32<4>;
+	type Signature = Signature;
+	type Registrars = RegistrarSet;
+}
+
+/// A deterministic sr25519 keypair for account `seed`, used to sign pre-signed grants in tests.
+pub fn pair(seed: u8) -> sr25519::Pair {
+	use sp_core::Pair as _;
+	sr25519::Pair::from_seed(&[seed; 32])
+}
+
+pub fn account(seed: u8) -> AccountId {
+	use sp_core::Pair as _;
+	pair(seed).public().into()
+}
+
+pub const RESERVATION_FEE: Balance = 10;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: sp_std::vec![(account(1), 1_000), (account(2), 1_000), (account(3), 1_000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	pallet_ans::GenesisConfig::<Test> {
+		reservation_fee: RESERVATION_FEE,
+		authorized_signers: sp_std::vec![account(1)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext: sp_io::TestExternalities = t.into();
+	// Events are not recorded at block 0, so tests start at block 1.
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
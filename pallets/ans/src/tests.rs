@@ -0,0 +1,344 @@
+use crate::{mock::*, Error, Event, PreSignedGrant};
+use frame_support::{assert_noop, assert_ok};
+
+fn name(s: &str) -> Vec<u8> {
+	s.as_bytes().to_vec()
+}
+
+fn run_to_block(n: u64) {
+	System::set_block_number(n);
+}
+
+#[test]
+fn reserve_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+
+		let entry = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+		assert_eq!(entry.owner, account(2));
+		assert_eq!(entry.deposit, RESERVATION_FEE);
+		assert_eq!(Balances::reserved_balance(&account(2)), RESERVATION_FEE);
+		assert_eq!(Ans::get_names_of(account(2)).into_inner(), vec![name("alice").try_into().unwrap()]);
+		System::assert_last_event(Event::Reserved { who: account(2), name: name("alice") }.into());
+	});
+}
+
+#[test]
+fn reserve_rejects_short_and_already_taken_names() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Ans::reserve(RuntimeOrigin::signed(account(2)), name("ab")),
+			Error::<Test>::TooShort
+		);
+
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_noop!(
+			Ans::reserve(RuntimeOrigin::signed(account(3)), name("alice")),
+			Error::<Test>::AlreadyReserved
+		);
+	});
+}
+
+#[test]
+fn reserve_reclaims_an_expired_name() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		run_to_block(100);
+
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(3)), name("alice")));
+		let entry = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+		assert_eq!(entry.owner, account(3));
+		assert_eq!(Ans::get_names_of(account(2)).into_inner(), Vec::<_>::new());
+		assert_eq!(Balances::reserved_balance(&account(2)), 0);
+	});
+}
+
+#[test]
+fn reserve_is_gated_by_membership_when_required() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::set_membership_required(RuntimeOrigin::root(), true));
+
+		assert_noop!(
+			Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")),
+			Error::<Test>::NotAllowed
+		);
+
+		set_registrars(vec![account(2)]);
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+
+		System::assert_has_event(Event::MembershipRequiredSet { required: true }.into());
+	});
+}
+
+#[test]
+fn transfer_to_moves_ownership_and_reserve() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_ok!(Ans::transfer_to(RuntimeOrigin::signed(account(2)), name("alice"), account(3)));
+
+		let entry = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+		assert_eq!(entry.owner, account(3));
+		assert_eq!(Balances::reserved_balance(&account(2)), 0);
+		assert_eq!(Balances::reserved_balance(&account(3)), RESERVATION_FEE);
+		assert_eq!(Ans::get_names_of(account(2)).into_inner(), Vec::<_>::new());
+		assert_eq!(Ans::get_names_of(account(3)).into_inner(), vec![name("alice").try_into().unwrap()]);
+	});
+}
+
+#[test]
+fn transfer_to_rejects_non_owner_and_self_transfer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+
+		assert_noop!(
+			Ans::transfer_to(RuntimeOrigin::signed(account(3)), name("alice"), account(3)),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			Ans::transfer_to(RuntimeOrigin::signed(account(2)), name("alice"), account(2)),
+			Error::<Test>::CannotTransferToSelf
+		);
+
+		// The failed self-transfer must not have corrupted the reverse index.
+		assert_eq!(Ans::get_names_of(account(2)).into_inner(), vec![name("alice").try_into().unwrap()]);
+	});
+}
+
+#[test]
+fn release_returns_deposit_and_clears_storage() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_ok!(Ans::release(RuntimeOrigin::signed(account(2)), name("alice")));
+
+		assert!(Ans::get_entry(name("alice").try_into().unwrap()).is_none());
+		assert_eq!(Balances::reserved_balance(&account(2)), 0);
+		assert_eq!(Ans::get_names_of(account(2)).into_inner(), Vec::<_>::new());
+	});
+}
+
+#[test]
+fn release_rejects_non_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_noop!(
+			Ans::release(RuntimeOrigin::signed(account(3)), name("alice")),
+			Error::<Test>::NotOwner
+		);
+	});
+}
+
+#[test]
+fn renew_extends_the_lease_and_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		let before = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+
+		assert_ok!(Ans::renew(RuntimeOrigin::signed(account(2)), name("alice")));
+		let after = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+
+		assert_eq!(after.expires_at, before.expires_at + 10);
+		assert_eq!(after.deposit, before.deposit + RESERVATION_FEE);
+		assert_eq!(Balances::reserved_balance(&account(2)), RESERVATION_FEE * 2);
+	});
+}
+
+#[test]
+fn claim_presigned_redeems_a_valid_grant() {
+	new_test_ext().execute_with(|| {
+		let signer = pair(1);
+		let data = PreSignedGrant { name: name("alice"), recipient: account(2), deadline: 50 };
+		let signature = sign(&signer, &data);
+
+		assert_ok!(Ans::claim_presigned(
+			RuntimeOrigin::signed(account(3)),
+			data,
+			signature,
+			account(1),
+		));
+
+		let entry = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+		assert_eq!(entry.owner, account(2));
+		assert_eq!(entry.deposit, 0);
+	});
+}
+
+#[test]
+fn claim_presigned_rejects_unauthorized_signer_and_replay() {
+	new_test_ext().execute_with(|| {
+		let impostor = pair(9);
+		let data = PreSignedGrant { name: name("alice"), recipient: account(2), deadline: 50 };
+		let signature = sign(&impostor, &data);
+
+		assert_noop!(
+			Ans::claim_presigned(RuntimeOrigin::signed(account(3)), data, signature, account(9)),
+			Error::<Test>::UnauthorizedSigner
+		);
+
+		let signer = pair(1);
+		let data = PreSignedGrant { name: name("alice"), recipient: account(2), deadline: 50 };
+		let signature = sign(&signer, &data);
+		assert_ok!(Ans::claim_presigned(
+			RuntimeOrigin::signed(account(3)),
+			data.clone(),
+			signature.clone(),
+			account(1),
+		));
+		assert_noop!(
+			Ans::claim_presigned(RuntimeOrigin::signed(account(3)), data, signature, account(1)),
+			Error::<Test>::GrantAlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn claim_presigned_rejects_bad_signature_and_expired_deadline() {
+	new_test_ext().execute_with(|| {
+		let signer = pair(1);
+		let data = PreSignedGrant { name: name("alice"), recipient: account(2), deadline: 50 };
+		let mut other_data = data.clone();
+		other_data.recipient = account(3);
+		let signature = sign(&signer, &other_data);
+
+		assert_noop!(
+			Ans::claim_presigned(RuntimeOrigin::signed(account(3)), data, signature, account(1)),
+			Error::<Test>::InvalidSignature
+		);
+
+		run_to_block(51);
+		let data = PreSignedGrant { name: name("bob"), recipient: account(2), deadline: 50 };
+		let signature = sign(&signer, &data);
+		assert_noop!(
+			Ans::claim_presigned(RuntimeOrigin::signed(account(3)), data, signature, account(1)),
+			Error::<Test>::GrantExpired
+		);
+	});
+}
+
+#[test]
+fn claim_presigned_allows_reuse_of_a_name_after_release() {
+	new_test_ext().execute_with(|| {
+		let signer = pair(1);
+		let data = PreSignedGrant { name: name("alice"), recipient: account(2), deadline: 50 };
+		let signature = sign(&signer, &data);
+		assert_ok!(Ans::claim_presigned(RuntimeOrigin::signed(account(3)), data, signature, account(1)));
+		assert_ok!(Ans::release(RuntimeOrigin::signed(account(2)), name("alice")));
+
+		// Once the name is back in the pool, a fresh grant for it must not be blocked by the
+		// first claim's replay protection.
+		let data = PreSignedGrant { name: name("alice"), recipient: account(3), deadline: 200 };
+		let signature = sign(&signer, &data);
+		assert_ok!(Ans::claim_presigned(RuntimeOrigin::signed(account(2)), data, signature, account(1)));
+		assert_eq!(Ans::get_entry(name("alice").try_into().unwrap()).unwrap().owner, account(3));
+	});
+}
+
+#[test]
+fn list_unlist_and_buy_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_ok!(Ans::list_for_sale(RuntimeOrigin::signed(account(2)), name("alice"), 100));
+
+		assert_noop!(
+			Ans::buy(RuntimeOrigin::signed(account(2)), name("alice")),
+			Error::<Test>::CannotBuyOwnName
+		);
+
+		assert_ok!(Ans::buy(RuntimeOrigin::signed(account(3)), name("alice")));
+		let entry = Ans::get_entry(name("alice").try_into().unwrap()).unwrap();
+		assert_eq!(entry.owner, account(3));
+		assert_eq!(Balances::free_balance(&account(2)), 1_000 - RESERVATION_FEE + 100 + RESERVATION_FEE);
+		assert_eq!(Balances::free_balance(&account(3)), 1_000 - 100 - RESERVATION_FEE);
+		assert_eq!(Balances::reserved_balance(&account(3)), RESERVATION_FEE);
+		assert!(Ans::get_listing(name("alice").try_into().unwrap()).is_none());
+	});
+}
+
+#[test]
+fn unlist_requires_an_existing_listing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_noop!(
+			Ans::unlist(RuntimeOrigin::signed(account(2)), name("alice")),
+			Error::<Test>::NotListed
+		);
+	});
+}
+
+#[test]
+fn buy_rejects_a_stale_listing_on_an_expired_lease() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("alice")));
+		assert_ok!(Ans::list_for_sale(RuntimeOrigin::signed(account(2)), name("alice"), 100));
+		run_to_block(100);
+
+		assert_noop!(
+			Ans::buy(RuntimeOrigin::signed(account(3)), name("alice")),
+			Error::<Test>::NotFound
+		);
+	});
+}
+
+#[test]
+fn reserve_transfer_buy_and_claim_enforce_max_names_per_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("name0")));
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("name1")));
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("name2")));
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(2)), name("name3")));
+
+		assert_noop!(
+			Ans::reserve(RuntimeOrigin::signed(account(2)), name("name4")),
+			Error::<Test>::TooManyNames
+		);
+		assert!(Ans::get_entry(name("name4").try_into().unwrap()).is_none());
+		assert_eq!(Ans::get_names_of(account(2)).len(), 4);
+
+		assert_ok!(Ans::reserve(RuntimeOrigin::signed(account(3)), name("spare")));
+		assert_noop!(
+			Ans::transfer_to(RuntimeOrigin::signed(account(3)), name("spare"), account(2)),
+			Error::<Test>::TooManyNames
+		);
+		assert_eq!(Ans::get_entry(name("spare").try_into().unwrap()).unwrap().owner, account(3));
+
+		assert_ok!(Ans::list_for_sale(RuntimeOrigin::signed(account(3)), name("spare"), 50));
+		assert_noop!(
+			Ans::buy(RuntimeOrigin::signed(account(2)), name("spare")),
+			Error::<Test>::TooManyNames
+		);
+		assert_eq!(Ans::get_entry(name("spare").try_into().unwrap()).unwrap().owner, account(3));
+		assert!(Ans::get_listing(name("spare").try_into().unwrap()).is_some());
+
+		let signer = pair(1);
+		let data = PreSignedGrant { name: name("granted"), recipient: account(2), deadline: 50 };
+		let signature = sign(&signer, &data);
+		assert_noop!(
+			Ans::claim_presigned(RuntimeOrigin::signed(account(3)), data, signature, account(1)),
+			Error::<Test>::TooManyNames
+		);
+		assert!(Ans::get_entry(name("granted").try_into().unwrap()).is_none());
+
+		// None of the failed attempts should have perturbed account(2)'s reverse index.
+		assert_eq!(Ans::get_names_of(account(2)).len(), 4);
+	});
+}
+
+#[test]
+fn grant_signers_can_be_added_and_removed_at_runtime() {
+	new_test_ext().execute_with(|| {
+		assert!(!Ans::is_authorized_signer(account(2)));
+
+		assert_ok!(Ans::add_grant_signer(RuntimeOrigin::root(), account(2)));
+		assert!(Ans::is_authorized_signer(account(2)));
+		System::assert_last_event(Event::GrantSignerAdded { who: account(2) }.into());
+
+		assert_ok!(Ans::remove_grant_signer(RuntimeOrigin::root(), account(2)));
+		assert!(!Ans::is_authorized_signer(account(2)));
+		System::assert_last_event(Event::GrantSignerRemoved { who: account(2) }.into());
+	});
+}
+
+fn sign(pair: &sp_core::sr25519::Pair, data: &PreSignedGrant<AccountId, u64>) -> Signature {
+	use codec::Encode;
+	use sp_core::Pair as _;
+	Signature::from(pair.sign(&data.encode()[..]))
+}